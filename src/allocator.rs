@@ -1,17 +1,19 @@
 pub mod bump;
+pub mod fixed_size_block;
+pub mod linked_list;
 
 use alloc::alloc::{GlobalAlloc, Layout};
 use bump::BumpAllocator;
 use core::ptr::null_mut;
 
 use x86_64::{
-    structures::paging::{
-        mapper::MapToError, page::PageRangeInclusive, FrameAllocator, Mapper, Page, PageTableFlags,
-        Size4KiB,
-    },
+    structures::paging::{mapper::MapToError, FrameAllocator, Mapper, Page, PageTableFlags, Size4KiB},
     VirtAddr,
 };
 
+// Swap in `Locked<fixed_size_block::FixedSizeBlockAllocator>` (with
+// `FixedSizeBlockAllocator::new()`) here for O(1) alloc/dealloc of common
+// sizes with real memory reuse, at the cost of some internal fragmentation.
 #[global_allocator]
 static ALLOCATOR: Locked<BumpAllocator> = Locked::new(BumpAllocator::new());
 
@@ -54,39 +56,86 @@ fn _align_up(addr: usize, align: usize) -> usize {
 }
 
 pub const HEAP_START: usize = 0x_4444_4444_0000;
-pub const HEAP_SIZE: usize = 100 * 1024; // 100 KiB
 
-pub fn init_heap(
+/// Upper bound on the heap size, regardless of how much physical memory is
+/// detected. It is also the size of the reserved heap window starting at
+/// `HEAP_START` that `is_heap_address` recognises.
+pub const MAX_HEAP_SIZE: usize = 16 * 1024 * 1024; // 16 MiB
+
+/// Picks a heap size for the given amount of usable physical memory: half
+/// of it, clamped to `MAX_HEAP_SIZE`.
+fn heap_size_for(usable_memory: u64) -> usize {
+    let half = (usable_memory / 2) as usize;
+    half.min(MAX_HEAP_SIZE)
+}
+
+/// Maps a single page, backing it with a freshly allocated frame, and
+/// flushes it into the TLB. Shared by `init_heap`'s eager setup of the
+/// heap's first page and `handle_heap_page_fault`'s lazy growth of the
+/// rest of the heap window.
+pub fn map_next(
+    page: Page<Size4KiB>,
     mapper: &mut impl Mapper<Size4KiB>,
     frame_allocator: &mut impl FrameAllocator<Size4KiB>,
 ) -> Result<(), MapToError<Size4KiB>> {
-    for page in page_range() {
-        let frame = frame_allocator
-            .allocate_frame()
-            .ok_or(MapToError::FrameAllocationFailed)?;
+    let frame = frame_allocator
+        .allocate_frame()
+        .ok_or(MapToError::FrameAllocationFailed)?;
 
-        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+    let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
 
-        unsafe {
-            mapper.map_to(page, frame, flags, frame_allocator)?.flush();
-        }
+    unsafe {
+        mapper.map_to(page, frame, flags, frame_allocator)?.flush();
     }
 
+    Ok(())
+}
+
+/// Sets up the heap without mapping it upfront: only the first page is
+/// mapped eagerly, since allocators that keep a free list in the heap (the
+/// linked-list and fixed-size-block ones) write their initial bookkeeping
+/// there on `init`. The allocator is still told about the full
+/// `heap_size`, so it can hand out addresses across all of it;
+/// `handle_heap_page_fault` maps the backing frame for the rest of the
+/// pages the first time each of them is actually touched.
+pub fn init_heap(
+    mapper: &mut impl Mapper<Size4KiB>,
+    frame_allocator: &mut impl FrameAllocator<Size4KiB>,
+    usable_memory: u64,
+) -> Result<(), MapToError<Size4KiB>> {
+    let heap_size = heap_size_for(usable_memory);
+    let heap_start_page = Page::containing_address(VirtAddr::new(HEAP_START as u64));
+
+    map_next(heap_start_page, mapper, frame_allocator)?;
+
     unsafe {
-        ALLOCATOR.lock().init(HEAP_START, HEAP_SIZE);
+        ALLOCATOR.lock().init(HEAP_START, heap_size);
     }
 
     Ok(())
 }
 
-fn page_range() -> PageRangeInclusive {
-    let heap_start = VirtAddr::new(HEAP_START as u64);
-    let heap_start_page = Page::containing_address(heap_start);
-
-    let heap_end = heap_start + HEAP_SIZE - 1u64;
-    let heap_end_page = Page::containing_address(heap_end);
+/// Whether `addr` falls inside the reserved heap window, regardless of
+/// whether the page backing it has been mapped yet. The page-fault
+/// interrupt handler should check this before calling
+/// `handle_heap_page_fault`, so unrelated faults still fall through to the
+/// normal "unhandled fault" path.
+pub fn is_heap_address(addr: VirtAddr) -> bool {
+    let addr = addr.as_u64() as usize;
+    addr >= HEAP_START && addr < HEAP_START + MAX_HEAP_SIZE
+}
 
-    Page::range_inclusive(heap_start_page, heap_end_page)
+/// Page-fault hook for the heap window: maps a fresh frame for the page
+/// containing `fault_addr` so the faulting instruction can simply be
+/// retried. Only meant to be called once `is_heap_address` has confirmed
+/// the fault belongs to the heap.
+pub fn handle_heap_page_fault(
+    fault_addr: VirtAddr,
+    mapper: &mut impl Mapper<Size4KiB>,
+    frame_allocator: &mut impl FrameAllocator<Size4KiB>,
+) -> Result<(), MapToError<Size4KiB>> {
+    let page = Page::containing_address(fault_addr);
+    map_next(page, mapper, frame_allocator)
 }
 
 pub struct Dummy;
@@ -0,0 +1,68 @@
+use super::{align_up, Locked};
+use alloc::alloc::{GlobalAlloc, Layout};
+use core::ptr::null_mut;
+
+/// The simplest possible allocator: a pointer that only ever moves forward
+/// through the heap. Individual allocations can't be freed on their own,
+/// but `allocations` tracks how many are still live, and once it drops
+/// back to zero the whole arena is known to be empty again, so `next` can
+/// be reset to `heap_start` and reused from scratch.
+pub struct BumpAllocator {
+    heap_start: usize,
+    heap_end: usize,
+    next: usize,
+    allocations: usize,
+}
+
+impl BumpAllocator {
+    /// Creates an empty allocator. Call `init` before using it.
+    pub const fn new() -> Self {
+        BumpAllocator {
+            heap_start: 0,
+            heap_end: 0,
+            next: 0,
+            allocations: 0,
+        }
+    }
+
+    /// Initializes the allocator with the given heap bounds.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `[heap_start, heap_start + heap_size)`
+    /// is valid, unused memory, and that `init` is only called once.
+    pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
+        self.heap_start = heap_start;
+        self.heap_end = heap_start + heap_size;
+        self.next = heap_start;
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<BumpAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut bump = self.lock();
+
+        let alloc_start = align_up(bump.next, layout.align());
+        let alloc_end = match alloc_start.checked_add(layout.size()) {
+            Some(end) => end,
+            None => return null_mut(),
+        };
+
+        if alloc_end > bump.heap_end {
+            null_mut()
+        } else {
+            bump.next = alloc_end;
+            bump.allocations += 1;
+            alloc_start as *mut u8
+        }
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+        let mut bump = self.lock();
+
+        bump.allocations -= 1;
+        if bump.allocations == 0 {
+            bump.next = bump.heap_start;
+        }
+    }
+}
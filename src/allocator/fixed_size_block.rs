@@ -0,0 +1,108 @@
+use super::Locked;
+use alloc::alloc::{GlobalAlloc, Layout};
+use core::{mem, ptr, ptr::NonNull};
+use linked_list_allocator::Heap;
+
+/// The block sizes we maintain free lists for. Powers of two so every size
+/// class is also a valid alignment, which means a block handed out of a
+/// given class is always aligned for any request rounded into that class.
+const BLOCK_SIZES: &[usize] = &[8, 16, 32, 64, 128, 256, 512, 1024, 2048];
+
+struct ListNode {
+    next: Option<&'static mut ListNode>,
+}
+
+/// Picks the smallest block size able to hold a request of this layout, or
+/// `None` if the request is bigger than our largest class (in which case
+/// it falls straight through to the fallback allocator).
+fn list_index(layout: &Layout) -> Option<usize> {
+    let required_block_size = layout.size().max(layout.align());
+    BLOCK_SIZES.iter().position(|&s| s >= required_block_size)
+}
+
+/// A fixed set of singly-linked free lists, one per block-size class, with
+/// a heap-based fallback allocator for requests too large for any class.
+///
+/// Alloc/dealloc of common small sizes are O(1) once their free list is
+/// warm; an allocation whose class has no free block (and any oversized
+/// request) goes to the slower fallback allocator.
+pub struct FixedSizeBlockAllocator {
+    list_heads: [Option<&'static mut ListNode>; BLOCK_SIZES.len()],
+    fallback_allocator: Heap,
+}
+
+impl FixedSizeBlockAllocator {
+    /// Creates an empty allocator. Call `init` before using it.
+    pub const fn new() -> Self {
+        const EMPTY: Option<&'static mut ListNode> = None;
+        FixedSizeBlockAllocator {
+            list_heads: [EMPTY; BLOCK_SIZES.len()],
+            fallback_allocator: Heap::empty(),
+        }
+    }
+
+    /// Initializes the allocator with the given heap bounds.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `[heap_start, heap_start + heap_size)`
+    /// is valid, unused memory, and that `init` is only called once.
+    pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
+        self.fallback_allocator
+            .init(heap_start as *mut u8, heap_size);
+    }
+
+    fn fallback_alloc(&mut self, layout: Layout) -> *mut u8 {
+        match self.fallback_allocator.allocate_first_fit(layout) {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<FixedSizeBlockAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut allocator = self.lock();
+
+        match list_index(&layout) {
+            Some(index) => match allocator.list_heads[index].take() {
+                Some(node) => {
+                    allocator.list_heads[index] = node.next.take();
+                    node as *mut ListNode as *mut u8
+                }
+                None => {
+                    // No block in this class is free yet, so carve one out
+                    // of the fallback allocator using the class's own size
+                    // and alignment (both equal, since sizes are powers of 2).
+                    let block_size = BLOCK_SIZES[index];
+                    let block_align = block_size;
+                    let layout = Layout::from_size_align(block_size, block_align).unwrap();
+                    allocator.fallback_alloc(layout)
+                }
+            },
+            None => allocator.fallback_alloc(layout),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let mut allocator = self.lock();
+
+        match list_index(&layout) {
+            Some(index) => {
+                assert!(mem::size_of::<ListNode>() <= BLOCK_SIZES[index]);
+                assert!(mem::align_of::<ListNode>() <= BLOCK_SIZES[index]);
+
+                let new_node = ListNode {
+                    next: allocator.list_heads[index].take(),
+                };
+                let new_node_ptr = ptr as *mut ListNode;
+                new_node_ptr.write(new_node);
+                allocator.list_heads[index] = Some(&mut *new_node_ptr);
+            }
+            None => {
+                let ptr = NonNull::new(ptr).unwrap();
+                allocator.fallback_allocator.deallocate(ptr, layout);
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use super::{align_up, Locked};
+use alloc::alloc::{GlobalAlloc, Layout};
+use core::mem;
+use core::ptr;
+
+/// A free region, stored inline in its own first bytes. `size` covers the
+/// whole region including the node header itself.
+struct ListNode {
+    size: usize,
+    next: Option<&'static mut ListNode>,
+}
+
+impl ListNode {
+    const fn new(size: usize) -> Self {
+        ListNode { size, next: None }
+    }
+
+    fn start_addr(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn end_addr(&self) -> usize {
+        self.start_addr() + self.size
+    }
+}
+
+/// A free-list allocator that can satisfy arbitrary sizes and reclaim
+/// freed memory, unlike the bump allocator.
+///
+/// Free regions are kept as an intrusive singly-linked list, address-sorted
+/// so that adjacent regions can be coalesced back into one on `dealloc`.
+/// Every allocation is rounded up to at least `size_of::<ListNode>()` bytes
+/// and `align_of::<ListNode>()` alignment, since a freed region must be able
+/// to host a `ListNode` again.
+pub struct LinkedListAllocator {
+    head: ListNode,
+}
+
+impl LinkedListAllocator {
+    /// Creates an empty allocator. Call `init` before using it.
+    pub const fn new() -> Self {
+        LinkedListAllocator {
+            head: ListNode::new(0),
+        }
+    }
+
+    /// Initializes the allocator with the given heap bounds.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `[heap_start, heap_start + heap_size)`
+    /// is valid, unused memory, and that `init` is only called once.
+    pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
+        self.add_free_region(heap_start, heap_size);
+    }
+
+    /// Inserts a free region into the list, keeping the list sorted by
+    /// address so that `merge_adjacent_regions` only ever has to compare a
+    /// node against its immediate successor.
+    unsafe fn add_free_region(&mut self, addr: usize, size: usize) {
+        assert_eq!(align_up(addr, mem::align_of::<ListNode>()), addr);
+        assert!(size >= mem::size_of::<ListNode>());
+
+        let mut current = &mut self.head;
+        while let Some(ref next) = current.next {
+            if next.start_addr() > addr {
+                break;
+            }
+            current = current.next.as_mut().unwrap();
+        }
+
+        let mut node = ListNode::new(size);
+        node.next = current.next.take();
+        let node_ptr = addr as *mut ListNode;
+        node_ptr.write(node);
+        current.next = Some(&mut *node_ptr);
+    }
+
+    /// Looks for the first free region able to fit an allocation of `size`
+    /// with `align`, removing it from the list and returning it.
+    fn find_region(&mut self, size: usize, align: usize) -> Option<(&'static mut ListNode, usize)> {
+        let mut current = &mut self.head;
+
+        while let Some(ref mut region) = current.next {
+            if let Ok(alloc_start) = Self::alloc_from_region(region, size, align) {
+                let next = region.next.take();
+                let ret = Some((current.next.take().unwrap(), alloc_start));
+                current.next = next;
+                return ret;
+            } else {
+                current = current.next.as_mut().unwrap();
+            }
+        }
+
+        None
+    }
+
+    /// Tries to fit an allocation of `size`/`align` at the start of
+    /// `region` (after alignment padding), failing if it doesn't fit or if
+    /// it would leave a remainder too small to host a `ListNode`.
+    fn alloc_from_region(region: &ListNode, size: usize, align: usize) -> Result<usize, ()> {
+        let alloc_start = align_up(region.start_addr(), align);
+        let alloc_end = alloc_start.checked_add(size).ok_or(())?;
+
+        if alloc_end > region.end_addr() {
+            return Err(());
+        }
+
+        let excess_size = region.end_addr() - alloc_end;
+        if excess_size > 0 && excess_size < mem::size_of::<ListNode>() {
+            return Err(());
+        }
+
+        Ok(alloc_start)
+    }
+
+    /// Adjusts a layout so the resulting allocation is at least large and
+    /// aligned enough to later be reused as a `ListNode`.
+    fn size_align(layout: Layout) -> (usize, usize) {
+        let layout = layout
+            .align_to(mem::align_of::<ListNode>())
+            .expect("adjusting alignment failed")
+            .pad_to_align();
+        let size = layout.size().max(mem::size_of::<ListNode>());
+        (size, layout.align())
+    }
+
+    /// Folds adjacent free regions into one. Relies on `add_free_region`
+    /// keeping the list address-sorted, so any two regions worth merging
+    /// are already next to each other in the list.
+    fn merge_adjacent_regions(&mut self) {
+        let mut current: *mut ListNode = &mut self.head;
+
+        unsafe {
+            while let Some(region) = (*current).next.as_deref_mut() {
+                let should_merge = region
+                    .next
+                    .as_deref()
+                    .map_or(false, |next| region.end_addr() == next.start_addr());
+
+                if should_merge {
+                    let next_node = region.next.take().unwrap();
+                    region.size += next_node.size;
+                    region.next = next_node.next;
+                } else {
+                    current = region as *mut ListNode;
+                }
+            }
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (size, align) = LinkedListAllocator::size_align(layout);
+        let mut allocator = self.lock();
+
+        if let Some((region, alloc_start)) = allocator.find_region(size, align) {
+            let alloc_end = alloc_start.checked_add(size).expect("overflow");
+            let excess_size = region.end_addr() - alloc_end;
+            if excess_size > 0 {
+                allocator.add_free_region(alloc_end, excess_size);
+            }
+            alloc_start as *mut u8
+        } else {
+            ptr::null_mut()
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let (size, _) = LinkedListAllocator::size_align(layout);
+        let mut allocator = self.lock();
+        allocator.add_free_region(ptr as usize, size);
+        allocator.merge_adjacent_regions();
+    }
+}